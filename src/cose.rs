@@ -0,0 +1,165 @@
+//! CIP-8 message signing: builds a `COSE_Sign1` signature plus its
+//! accompanying `COSE_Key` over an arbitrary payload, per
+//! <https://github.com/cardano-foundation/CIPs/tree/master/CIP-0008>.
+
+use blake2::digest::{Update, VariableOutput};
+use blake2::Blake2bVar;
+use ciborium::value::Value;
+
+use crate::error::{SignerError, SignerResult};
+
+const COSE_ALG_EDDSA: i64 = -8;
+const COSE_KTY_OKP: i64 = 1;
+const COSE_CRV_ED25519: i64 = 6;
+
+/// Blake2b-224 hash, used when the caller asks for the payload to be hashed
+/// before it is embedded in the `Sig_structure`.
+fn blake2b_224(data: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2bVar::new(28).expect("28 is a valid blake2b output size");
+    hasher.update(data);
+    let mut out = vec![0u8; 28];
+    hasher.finalize_variable(&mut out).expect("fixed-size output buffer");
+    out
+}
+
+fn encode(value: &Value) -> SignerResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes)
+        .map_err(|e| SignerError::SigningFailed(format!("failed to CBOR-encode: {e}")))?;
+    Ok(bytes)
+}
+
+/// Builds and CBOR-encodes the COSE protected header map
+/// `{1: -8 (EdDSA), "address": <raw address bytes>}`.
+fn protected_header_bytes(address: &[u8]) -> SignerResult<Vec<u8>> {
+    let header = Value::Map(vec![
+        (Value::Integer(1.into()), Value::Integer(COSE_ALG_EDDSA.into())),
+        (
+            Value::Text("address".to_string()),
+            Value::Bytes(address.to_vec()),
+        ),
+    ]);
+    encode(&header)
+}
+
+/// Builds and CBOR-encodes the `Sig_structure` that is actually signed:
+/// `["Signature1", protected_bytes, h'', payload_bytes]`.
+pub(crate) fn sig_structure_bytes(protected: &[u8], payload: &[u8]) -> SignerResult<Vec<u8>> {
+    let sig_structure = Value::Array(vec![
+        Value::Text("Signature1".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+        Value::Bytes(payload.to_vec()),
+    ]);
+    encode(&sig_structure)
+}
+
+/// Builds and CBOR-encodes the final `COSE_Sign1` structure:
+/// `[protected_bytes, {}, payload_bytes, signature]`.
+fn cose_sign1_bytes(protected: &[u8], payload: &[u8], signature: &[u8]) -> SignerResult<Vec<u8>> {
+    let cose_sign1 = Value::Array(vec![
+        Value::Bytes(protected.to_vec()),
+        Value::Map(vec![]),
+        Value::Bytes(payload.to_vec()),
+        Value::Bytes(signature.to_vec()),
+    ]);
+    encode(&cose_sign1)
+}
+
+/// Builds and CBOR-encodes a `COSE_Key` for an Ed25519 public key:
+/// `{1: 1 (OKP), 3: -8 (EdDSA), -1: 6 (Ed25519), -2: <public key bytes>}`.
+fn cose_key_bytes(public_key: &[u8]) -> SignerResult<Vec<u8>> {
+    let cose_key = Value::Map(vec![
+        (Value::Integer(1.into()), Value::Integer(COSE_KTY_OKP.into())),
+        (Value::Integer(3.into()), Value::Integer(COSE_ALG_EDDSA.into())),
+        (Value::Integer((-1).into()), Value::Integer(COSE_CRV_ED25519.into())),
+        (
+            Value::Integer((-2).into()),
+            Value::Bytes(public_key.to_vec()),
+        ),
+    ]);
+    encode(&cose_key)
+}
+
+/// Signs `payload` as a CIP-8 `COSE_Sign1` message for `address`, returning
+/// the hex-encoded `(COSE_Sign1, COSE_Key)` pair.
+///
+/// When `hashed` is set, the payload is blake2b-224 hashed before being
+/// embedded in the `Sig_structure`, matching the CIP-8 `hashPayload` option.
+pub fn sign_data(
+    sign: impl FnOnce(&[u8]) -> Vec<u8>,
+    public_key: &[u8],
+    address: &[u8],
+    payload: &[u8],
+    hashed: bool,
+) -> SignerResult<(String, String)> {
+    let payload = if hashed { blake2b_224(payload) } else { payload.to_vec() };
+
+    let protected = protected_header_bytes(address)?;
+    let to_sign = sig_structure_bytes(&protected, &payload)?;
+    let signature = sign(&to_sign);
+    let cose_sign1 = cose_sign1_bytes(&protected, &payload, &signature)?;
+    let cose_key = cose_key_bytes(public_key)?;
+
+    Ok((hex::encode(cose_sign1), hex::encode(cose_key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(hex_str: &str) -> Value {
+        ciborium::de::from_reader(hex::decode(hex_str).unwrap().as_slice()).unwrap()
+    }
+
+    #[test]
+    fn sign_data_signs_the_sig_structure_not_the_raw_payload() {
+        let address = vec![1u8; 29];
+        let payload = b"login-challenge".to_vec();
+        let expected_to_sign =
+            sig_structure_bytes(&protected_header_bytes(&address).unwrap(), &payload).unwrap();
+
+        let mut signed_bytes = None;
+        let (cose_sign1_hex, cose_key_hex) = sign_data(
+            |data| {
+                signed_bytes = Some(data.to_vec());
+                vec![9u8; 64]
+            },
+            &[2u8; 32],
+            &address,
+            &payload,
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(signed_bytes.unwrap(), expected_to_sign);
+
+        match decode(&cose_sign1_hex) {
+            Value::Array(items) => {
+                assert_eq!(items.len(), 4);
+                assert_eq!(items[2], Value::Bytes(payload));
+                assert_eq!(items[3], Value::Bytes(vec![9u8; 64]));
+            }
+            _ => panic!("expected a COSE_Sign1 array"),
+        }
+
+        match decode(&cose_key_hex) {
+            Value::Map(entries) => {
+                assert!(entries.contains(&(Value::Integer((-2).into()), Value::Bytes(vec![2u8; 32]))));
+            }
+            _ => panic!("expected a COSE_Key map"),
+        }
+    }
+
+    #[test]
+    fn hashed_option_embeds_the_blake2b_224_digest_instead_of_the_payload() {
+        let payload = b"a payload too large to sign directly".to_vec();
+        let (cose_sign1_hex, _) =
+            sign_data(|_| vec![0u8; 64], &[0u8; 32], &[0u8; 29], &payload, true).unwrap();
+
+        match decode(&cose_sign1_hex) {
+            Value::Array(items) => assert_eq!(items[2], Value::Bytes(blake2b_224(&payload))),
+            _ => panic!("expected a COSE_Sign1 array"),
+        }
+    }
+}
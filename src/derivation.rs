@@ -0,0 +1,58 @@
+//! Validates CIP-1852-style derivation paths (e.g. `m/1852'/1815'/0'/0/0`)
+//! before they reach `whisky_wallet`, so a malformed path is reported as
+//! `SignerError::InvalidDerivationPath` instead of silently falling through
+//! to whatever `DerivationIndices::from_str` happens to do with it.
+
+use crate::error::{SignerError, SignerResult};
+
+pub fn validate(derivation_path: &str) -> SignerResult<()> {
+    let err = |reason: &str| {
+        SignerError::InvalidDerivationPath(format!("{reason}: \"{derivation_path}\""))
+    };
+
+    let mut segments = derivation_path.split('/');
+    if segments.next() != Some("m") {
+        return Err(err("derivation path must start with \"m\""));
+    }
+
+    let mut segment_count = 0;
+    for segment in segments {
+        segment_count += 1;
+        segment
+            .strip_suffix('\'')
+            .unwrap_or(segment)
+            .parse::<u32>()
+            .map_err(|_| err("segments must be unsigned integers, optionally hardened with '"))?;
+    }
+
+    if segment_count == 0 {
+        return Err(err("derivation path must contain at least one segment"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_a_standard_cip1852_path() {
+        assert!(validate("m/1852'/1815'/0'/0/0").is_ok());
+    }
+
+    #[test]
+    fn rejects_a_path_missing_the_m_prefix() {
+        assert!(validate("1852'/1815'/0'/0/0").is_err());
+    }
+
+    #[test]
+    fn rejects_a_non_numeric_segment() {
+        assert!(validate("m/1852'/abc/0'/0/0").is_err());
+    }
+
+    #[test]
+    fn rejects_an_empty_path() {
+        assert!(validate("m").is_err());
+        assert!(validate("").is_err());
+    }
+}
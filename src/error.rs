@@ -0,0 +1,53 @@
+use std::fmt;
+
+/// Errors surfaced across the cxx FFI boundary.
+///
+/// C++ callers receive these as a thrown exception (cxx converts any
+/// `Display`-able error into a `rust::Error`), so the `Display` impl below is
+/// the stable, user-facing message and should not be reworded lightly.
+#[derive(Debug)]
+pub enum SignerError {
+    InvalidMnemonic(String),
+    InvalidRootKey(String),
+    InvalidEd25519Key(String),
+    InvalidDerivationPath(String),
+    InvalidTxHex(String),
+    KeystoreMacMismatch,
+    SigningFailed(String),
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::InvalidMnemonic(msg) => write!(f, "invalid mnemonic: {msg}"),
+            SignerError::InvalidRootKey(msg) => write!(f, "invalid root private key: {msg}"),
+            SignerError::InvalidEd25519Key(msg) => write!(f, "invalid ed25519 key: {msg}"),
+            SignerError::InvalidDerivationPath(msg) => {
+                write!(f, "invalid derivation path: {msg}")
+            }
+            SignerError::InvalidTxHex(msg) => write!(f, "invalid transaction hex: {msg}"),
+            SignerError::KeystoreMacMismatch => {
+                write!(f, "keystore MAC mismatch: wrong passphrase or corrupted file")
+            }
+            SignerError::SigningFailed(msg) => write!(f, "signing failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+pub type SignerResult<T> = std::result::Result<T, SignerError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_variant_names_its_own_kind_of_secret() {
+        assert!(SignerError::InvalidMnemonic("x".to_string()).to_string().contains("mnemonic"));
+        assert!(SignerError::InvalidRootKey("x".to_string()).to_string().contains("root private key"));
+        assert!(SignerError::InvalidEd25519Key("x".to_string()).to_string().contains("ed25519 key"));
+        assert!(SignerError::InvalidDerivationPath("x".to_string()).to_string().contains("derivation path"));
+        assert!(SignerError::KeystoreMacMismatch.to_string().contains("MAC mismatch"));
+    }
+}
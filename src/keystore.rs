@@ -0,0 +1,218 @@
+//! Encrypted-at-rest storage for signer secrets, in the style of the
+//! `eth-keystore` / Bitcoin Core descriptor-wallet JSON format: a
+//! scrypt-derived symmetric key wraps the secret with AES-128-CTR, and a MAC
+//! over the derived-key tail plus ciphertext detects tampering or a wrong
+//! passphrase before any decryption is attempted.
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use blake2::digest::{Update, VariableOutput};
+use blake2::Blake2bVar;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{SignerError, SignerResult};
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+const KEYSTORE_VERSION: u32 = 1;
+const DEFAULT_SCRYPT_N: u32 = 1 << 14;
+const DEFAULT_SCRYPT_R: u32 = 8;
+const DEFAULT_SCRYPT_P: u32 = 1;
+const SCRYPT_DKLEN: usize = 32;
+
+/// scrypt cost parameters for `encrypt`. `Default` matches go-ethereum's
+/// "standard" keystore cost (`N=2^14, r=8, p=1`).
+#[derive(Debug, Clone, Copy)]
+pub struct ScryptCost {
+    pub n: u32,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptCost {
+    fn default() -> Self {
+        ScryptCost { n: DEFAULT_SCRYPT_N, r: DEFAULT_SCRYPT_R, p: DEFAULT_SCRYPT_P }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ScryptParams {
+    n: u32,
+    r: u32,
+    p: u32,
+    dklen: usize,
+    salt: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Crypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: ScryptParams,
+    mac: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Keystore {
+    version: u32,
+    crypto: Crypto,
+}
+
+fn derive_key(passphrase: &str, params: &ScryptParams, salt: &[u8]) -> SignerResult<[u8; SCRYPT_DKLEN]> {
+    let log_n = params
+        .n
+        .checked_ilog2()
+        .filter(|&log_n| 1u32 << log_n == params.n)
+        .ok_or_else(|| SignerError::SigningFailed(format!(
+            "invalid scrypt params: n ({}) must be a nonzero power of two",
+            params.n
+        )))? as u8;
+    let scrypt_params = scrypt::Params::new(log_n, params.r, params.p, params.dklen)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid scrypt params: {e}")))?;
+    let mut derived = [0u8; SCRYPT_DKLEN];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &scrypt_params, &mut derived)
+        .map_err(|e| SignerError::SigningFailed(format!("scrypt derivation failed: {e}")))?;
+    Ok(derived)
+}
+
+fn mac(derived_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2bVar::new(32).expect("32 is a valid blake2b output size");
+    hasher.update(&derived_key[16..32]);
+    hasher.update(ciphertext);
+    let mut out = vec![0u8; 32];
+    hasher.finalize_variable(&mut out).expect("fixed-size output buffer");
+    out
+}
+
+/// Encrypts `secret` with `passphrase` using the given scrypt cost
+/// parameters, returning the keystore as a JSON string.
+pub fn encrypt(secret: &[u8], passphrase: &str, cost: ScryptCost) -> SignerResult<String> {
+    let mut salt = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let mut iv = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut iv);
+
+    let params = ScryptParams {
+        n: cost.n,
+        r: cost.r,
+        p: cost.p,
+        dklen: SCRYPT_DKLEN,
+        salt: hex::encode(salt),
+    };
+    let derived_key = derive_key(passphrase, &params, &salt)?;
+
+    let mut ciphertext = secret.to_vec();
+    let mut cipher = Aes128Ctr::new(derived_key[..16].into(), (&iv).into());
+    cipher.apply_keystream(&mut ciphertext);
+
+    let mac = mac(&derived_key, &ciphertext);
+
+    let keystore = Keystore {
+        version: KEYSTORE_VERSION,
+        crypto: Crypto {
+            cipher: "aes-128-ctr".to_string(),
+            cipherparams: CipherParams { iv: hex::encode(iv) },
+            ciphertext: hex::encode(ciphertext),
+            kdf: "scrypt".to_string(),
+            kdfparams: params,
+            mac: hex::encode(mac),
+        },
+    };
+
+    serde_json::to_string(&keystore)
+        .map_err(|e| SignerError::SigningFailed(format!("failed to serialize keystore: {e}")))
+}
+
+/// Decrypts a keystore JSON document with `passphrase`, verifying the MAC
+/// before attempting decryption.
+pub fn decrypt(keystore_json: &str, passphrase: &str) -> SignerResult<Vec<u8>> {
+    let keystore: Keystore = serde_json::from_str(keystore_json)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid keystore JSON: {e}")))?;
+
+    if keystore.version != KEYSTORE_VERSION {
+        return Err(SignerError::SigningFailed(format!(
+            "unsupported keystore version: {}",
+            keystore.version
+        )));
+    }
+
+    let salt = hex::decode(&keystore.crypto.kdfparams.salt)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid keystore salt: {e}")))?;
+    let iv = hex::decode(&keystore.crypto.cipherparams.iv)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid keystore iv: {e}")))?;
+    let mut ciphertext = hex::decode(&keystore.crypto.ciphertext)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid keystore ciphertext: {e}")))?;
+    let expected_mac = hex::decode(&keystore.crypto.mac)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid keystore mac: {e}")))?;
+
+    let derived_key = derive_key(passphrase, &keystore.crypto.kdfparams, &salt)?;
+
+    if mac(&derived_key, &ciphertext) != expected_mac {
+        return Err(SignerError::KeystoreMacMismatch);
+    }
+
+    let mut cipher = Aes128Ctr::new(derived_key[..16].into(), iv.as_slice().into());
+    cipher.apply_keystream(&mut ciphertext);
+    Ok(ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Small enough to keep the test suite fast; still exercises real scrypt.
+    const TEST_COST: ScryptCost = ScryptCost { n: 16, r: 8, p: 1 };
+
+    #[test]
+    fn decrypt_recovers_the_original_secret() {
+        let secret = b"root_xsk1...".to_vec();
+        let keystore_json = encrypt(&secret, "correct horse", TEST_COST).unwrap();
+        let recovered = decrypt(&keystore_json, "correct horse").unwrap();
+        assert_eq!(recovered, secret);
+    }
+
+    #[test]
+    fn decrypt_rejects_the_wrong_passphrase() {
+        let keystore_json = encrypt(b"root_xsk1...", "correct horse", TEST_COST).unwrap();
+        let err = decrypt(&keystore_json, "wrong passphrase").unwrap_err();
+        assert!(err.to_string().contains("MAC mismatch"));
+    }
+
+    #[test]
+    fn encrypt_honors_the_requested_scrypt_cost() {
+        let keystore_json = encrypt(b"secret", "pw", ScryptCost { n: 32, r: 4, p: 2 }).unwrap();
+        let keystore: Keystore = serde_json::from_str(&keystore_json).unwrap();
+        assert_eq!(keystore.crypto.kdfparams.n, 32);
+        assert_eq!(keystore.crypto.kdfparams.r, 4);
+        assert_eq!(keystore.crypto.kdfparams.p, 2);
+    }
+
+    #[test]
+    fn decrypt_rejects_wrong_passphrase_with_the_dedicated_mac_mismatch_error() {
+        let keystore_json = encrypt(b"root_xsk1...", "correct horse", TEST_COST).unwrap();
+        let err = decrypt(&keystore_json, "wrong passphrase").unwrap_err();
+        assert!(matches!(err, SignerError::KeystoreMacMismatch));
+    }
+
+    #[test]
+    fn encrypt_rejects_a_zero_scrypt_cost_instead_of_panicking() {
+        let err = encrypt(b"secret", "pw", ScryptCost { n: 0, r: 8, p: 1 }).unwrap_err();
+        assert!(err.to_string().contains("power of two"));
+    }
+
+    #[test]
+    fn decrypt_rejects_a_non_power_of_two_n_from_an_untrusted_document() {
+        let keystore_json = encrypt(b"secret", "pw", TEST_COST).unwrap();
+        let tampered = keystore_json.replace("\"n\":16", "\"n\":0");
+        assert_ne!(tampered, keystore_json, "test fixture did not contain the expected n field");
+        let err = decrypt(&tampered, "pw").unwrap_err();
+        assert!(err.to_string().contains("power of two"));
+    }
+}
@@ -3,55 +3,283 @@ use whisky_wallet::{
     WalletType,
 };
 
+mod cose;
+mod derivation;
+mod error;
+mod external;
+mod keystore;
+mod verify;
+mod witness;
+
+use error::{SignerError, SignerResult};
+use serde::{Deserialize, Serialize};
+
 #[cxx::bridge]
 mod ffi {
+    /// A CIP-8 `COSE_Sign1` signature together with the `COSE_Key` needed to
+    /// verify it, as returned by `Signer::sign_data`.
+    struct DataSignature {
+        cose_sign1_hex: String,
+        cose_key_hex: String,
+    }
+
     // Rust types and signatures exposed to C++.
     extern "Rust" {
         type Signer;
-        fn new_mnemonic_signer(mnemonic_phrase: &str, derivation_path: &str) -> Box<Signer>;
-        fn new_bech32_signer(root_private_key: &str, derivation_path: &str) -> Box<Signer>;
-        fn new_cli_signer(ed25519_key: &str) -> Box<Signer>;
-        fn sign_transaction(&mut self, tx_hex: &str) -> String;
-        fn get_public_key(&self) -> String;
+        fn new_mnemonic_signer(mnemonic_phrase: &str, derivation_path: &str)
+            -> Result<Box<Signer>>;
+        fn new_bech32_signer(root_private_key: &str, derivation_path: &str)
+            -> Result<Box<Signer>>;
+        fn new_cli_signer(ed25519_key: &str) -> Result<Box<Signer>>;
+        fn new_keystore_signer(keystore_json: &str, passphrase: &str) -> Result<Box<Signer>>;
+        fn new_external_signer(command: &str, args: &Vec<String>) -> Box<Signer>;
+        fn export_keystore(
+            &self,
+            passphrase: &str,
+            scrypt_n: u32,
+            scrypt_r: u32,
+            scrypt_p: u32,
+        ) -> Result<String>;
+        fn sign_transaction(&mut self, tx_hex: &str) -> Result<String>;
+        fn sign_partial(&self, tx_hex: &str) -> Result<String>;
+        fn sign_data(
+            &self,
+            payload_hex: &str,
+            address_hex: &str,
+            hashed: bool,
+        ) -> Result<DataSignature>;
+        fn get_public_key(&self) -> Result<String>;
     }
+
+    extern "Rust" {
+        fn combine_witnesses(tx_hex: &str, witnesses: &Vec<String>) -> Result<String>;
+        fn verify_signature(public_key_hex: &str, message_hex: &str, signature_hex: &str) -> Result<bool>;
+        fn verify_data(cose_sign1_hex: &str, cose_key_hex: &str) -> Result<bool>;
+    }
+}
+
+use ffi::DataSignature;
+
+/// The secret that backs a `Signer`, persisted (encrypted) by
+/// `export_keystore` and restored by `new_keystore_signer`.
+#[derive(Serialize, Deserialize)]
+struct SecretMaterial {
+    kind: String,
+    value: String,
+    derivation_path: Option<String>,
 }
 
-fn new_mnemonic_signer(mnemonic_phrase: &str, derivation_path: &str) -> Box<Signer> {
+fn new_mnemonic_signer(mnemonic_phrase: &str, derivation_path: &str) -> SignerResult<Box<Signer>> {
+    derivation::validate(derivation_path)?;
     let wallet = Wallet::new(WalletType::MnemonicWallet(MnemonicWallet {
         mnemonic_phrase: mnemonic_phrase.to_string(),
         derivation_indices: DerivationIndices::from_str(derivation_path),
     }));
-    let account = wallet.get_account().unwrap();
-    Box::new(Signer { account })
+    let account = wallet
+        .get_account()
+        .map_err(|e| SignerError::InvalidMnemonic(e.to_string()))?;
+    let secret = SecretMaterial {
+        kind: "mnemonic".to_string(),
+        value: mnemonic_phrase.to_string(),
+        derivation_path: Some(derivation_path.to_string()),
+    };
+    Ok(Box::new(Signer {
+        backend: Backend::Local { account, secret },
+    }))
 }
 
-fn new_bech32_signer(root_private_key: &str, derivation_path: &str) -> Box<Signer> {
+fn new_bech32_signer(root_private_key: &str, derivation_path: &str) -> SignerResult<Box<Signer>> {
+    derivation::validate(derivation_path)?;
     let wallet = Wallet::new(WalletType::RootKeyWallet(RootKeyWallet {
         root_key: root_private_key.to_string(),
         derivation_indices: DerivationIndices::from_str(derivation_path),
     }));
-    let account = wallet.get_account().unwrap();
-    Box::new(Signer { account })
+    let account = wallet
+        .get_account()
+        .map_err(|e| SignerError::InvalidRootKey(e.to_string()))?;
+    let secret = SecretMaterial {
+        kind: "root_key".to_string(),
+        value: root_private_key.to_string(),
+        derivation_path: Some(derivation_path.to_string()),
+    };
+    Ok(Box::new(Signer {
+        backend: Backend::Local { account, secret },
+    }))
 }
 
-fn new_cli_signer(ed25519_key: &str) -> Box<Signer> {
+fn new_cli_signer(ed25519_key: &str) -> SignerResult<Box<Signer>> {
     let wallet = Wallet::new_cli(ed25519_key);
-    let account = wallet.get_account().unwrap();
-    Box::new(Signer { account })
+    let account = wallet
+        .get_account()
+        .map_err(|e| SignerError::InvalidEd25519Key(e.to_string()))?;
+    let secret = SecretMaterial {
+        kind: "ed25519".to_string(),
+        value: ed25519_key.to_string(),
+        derivation_path: None,
+    };
+    Ok(Box::new(Signer {
+        backend: Backend::Local { account, secret },
+    }))
+}
+
+/// Creates a signer that delegates `sign_transaction` and `get_public_key`
+/// to an external executable instead of holding key material in-process,
+/// e.g. a Ledger/Trezor bridge or a remote HSM. `command` plus `args` (a
+/// device selector, a serial port, flags, ...) are spawned once per
+/// operation and speak the line-delimited JSON protocol documented in the
+/// `external` module.
+fn new_external_signer(command: &str, args: &Vec<String>) -> Box<Signer> {
+    Box::new(Signer {
+        backend: Backend::External { command: command.to_string(), args: args.clone() },
+    })
+}
+
+/// Rebuilds a `Signer` from a keystore document encrypted by
+/// `Signer::export_keystore`, failing with a distinct error on MAC mismatch
+/// (wrong passphrase or tampered file) before any secret is reconstructed.
+fn new_keystore_signer(keystore_json: &str, passphrase: &str) -> SignerResult<Box<Signer>> {
+    let secret_bytes = keystore::decrypt(keystore_json, passphrase)?;
+    let secret: SecretMaterial = serde_json::from_slice(&secret_bytes)
+        .map_err(|e| SignerError::SigningFailed(format!("corrupt keystore payload: {e}")))?;
+
+    match secret.kind.as_str() {
+        "mnemonic" => new_mnemonic_signer(
+            &secret.value,
+            secret.derivation_path.as_deref().unwrap_or(""),
+        ),
+        "root_key" => new_bech32_signer(
+            &secret.value,
+            secret.derivation_path.as_deref().unwrap_or(""),
+        ),
+        "ed25519" => new_cli_signer(&secret.value),
+        other => Err(SignerError::SigningFailed(format!(
+            "unknown keystore secret kind: {other}"
+        ))),
+    }
+}
+
+/// Where a `Signer` gets its signing capability from.
+enum Backend {
+    /// Key material lives in this process, derived via `whisky_wallet`.
+    Local { account: Account, secret: SecretMaterial },
+    /// Key material lives elsewhere; operations shell out to `command args`.
+    External { command: String, args: Vec<String> },
+}
+
+/// Merges vkey witnesses produced by separate `Signer::sign_partial` calls
+/// into `tx_hex`'s witness set, de-duplicating by public key. Lets an M-of-N
+/// native-script coordinator assemble a fully witnessed transaction from
+/// witnesses collected from independent signers.
+fn combine_witnesses(tx_hex: &str, witnesses: &Vec<String>) -> SignerResult<String> {
+    witness::combine_witnesses(tx_hex, witnesses)
+}
+
+/// Verifies a raw Ed25519 signature, e.g. a vkey witness, without needing
+/// the signer that produced it.
+fn verify_signature(public_key_hex: &str, message_hex: &str, signature_hex: &str) -> SignerResult<bool> {
+    verify::verify_signature(public_key_hex, message_hex, signature_hex)
+}
+
+/// Verifies a CIP-8 `COSE_Sign1` message produced by `Signer::sign_data`
+/// against its accompanying `COSE_Key`.
+fn verify_data(cose_sign1_hex: &str, cose_key_hex: &str) -> SignerResult<bool> {
+    verify::verify_data(cose_sign1_hex, cose_key_hex)
 }
 
 struct Signer {
-    account: Account,
+    backend: Backend,
 }
 
 impl Signer {
-    pub fn sign_transaction(&self, tx_hex: &str) -> String {
-        self.account.sign_transaction(tx_hex).unwrap_or_else(|_| {
-            panic!("Failed to sign transaction with the provided account");
-        })
+    pub fn sign_transaction(&self, tx_hex: &str) -> SignerResult<String> {
+        match &self.backend {
+            Backend::Local { account, .. } => account
+                .sign_transaction(tx_hex)
+                .map_err(|e| SignerError::SigningFailed(e.to_string())),
+            Backend::External { command, args } => external::sign_transaction(command, args, tx_hex),
+        }
+    }
+
+    /// Produces just this signer's vkey witness for `tx_hex`, without
+    /// assembling the final signed transaction. See `combine_witnesses` for
+    /// merging several signers' witnesses into one transaction.
+    pub fn sign_partial(&self, tx_hex: &str) -> SignerResult<String> {
+        match &self.backend {
+            Backend::Local { account, .. } => {
+                let hash = witness::hash_for_signing(tx_hex)?;
+                let signature = account.private_key.sign(&hash).to_bytes().to_vec();
+                witness::encode_vkey_witness(account.public_key.as_bytes(), &signature)
+            }
+            Backend::External { command, args } => external::sign_partial(command, args, tx_hex),
+        }
+    }
+
+    pub fn sign_data(
+        &self,
+        payload_hex: &str,
+        address_hex: &str,
+        hashed: bool,
+    ) -> SignerResult<DataSignature> {
+        let account = match &self.backend {
+            Backend::Local { account, .. } => account,
+            Backend::External { .. } => {
+                return Err(SignerError::SigningFailed(
+                    "sign_data is not supported by external signers".to_string(),
+                ))
+            }
+        };
+
+        let payload = hex::decode(payload_hex)
+            .map_err(|e| SignerError::SigningFailed(format!("invalid payload hex: {e}")))?;
+        let address = hex::decode(address_hex)
+            .map_err(|e| SignerError::SigningFailed(format!("invalid address hex: {e}")))?;
+        let public_key = account.public_key.as_bytes();
+
+        let (cose_sign1_hex, cose_key_hex) = cose::sign_data(
+            |data| account.private_key.sign(data).to_bytes().to_vec(),
+            public_key,
+            &address,
+            &payload,
+            hashed,
+        )?;
+
+        Ok(DataSignature { cose_sign1_hex, cose_key_hex })
+    }
+
+    /// Exports this signer's secret as an encrypted keystore JSON document.
+    /// Passing `0` for all of `scrypt_n`/`scrypt_r`/`scrypt_p` uses the
+    /// default cost; otherwise all three are used as given.
+    pub fn export_keystore(
+        &self,
+        passphrase: &str,
+        scrypt_n: u32,
+        scrypt_r: u32,
+        scrypt_p: u32,
+    ) -> SignerResult<String> {
+        let secret = match &self.backend {
+            Backend::Local { secret, .. } => secret,
+            Backend::External { .. } => {
+                return Err(SignerError::SigningFailed(
+                    "export_keystore is not supported by external signers".to_string(),
+                ))
+            }
+        };
+
+        let cost = if scrypt_n == 0 && scrypt_r == 0 && scrypt_p == 0 {
+            keystore::ScryptCost::default()
+        } else {
+            keystore::ScryptCost { n: scrypt_n, r: scrypt_r, p: scrypt_p }
+        };
+
+        let secret_bytes = serde_json::to_vec(secret)
+            .map_err(|e| SignerError::SigningFailed(format!("failed to serialize secret: {e}")))?;
+        keystore::encrypt(&secret_bytes, passphrase, cost)
     }
 
-    pub fn get_public_key(&self) -> String {
-        self.account.public_key.to_hex()
+    pub fn get_public_key(&self) -> SignerResult<String> {
+        match &self.backend {
+            Backend::Local { account, .. } => Ok(account.public_key.to_hex()),
+            Backend::External { command, args } => external::get_public_key(command, args),
+        }
     }
 }
@@ -0,0 +1,271 @@
+//! Signature verification for witnesses and CIP-8 signed messages, so
+//! integrators can confirm a signature without pulling in a separate
+//! Ed25519 library.
+
+use ciborium::value::{Integer, Value};
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+
+use crate::cose;
+use crate::error::{SignerError, SignerResult};
+
+/// Caps how deeply nested arrays/maps/tags may be before `check_item_depth`
+/// gives up, so a few hundred KB of nested single-byte array headers in a
+/// caller-supplied `cose_sign1_hex`/`cose_key_hex` can't stack-overflow the
+/// process via `ciborium`'s own recursive decoder. CIP-8 messages never come
+/// close to this in practice.
+const MAX_CBOR_NESTING_DEPTH: u32 = 64;
+
+fn cbor_argument(bytes: &[u8], pos: usize, info: u8) -> SignerResult<(u64, usize)> {
+    let err = || SignerError::SigningFailed("truncated cbor".to_string());
+    match info {
+        0..=23 => Ok((info as u64, pos)),
+        24 => Ok((*bytes.get(pos).ok_or_else(err)? as u64, pos + 1)),
+        25 => {
+            let b: [u8; 2] = bytes.get(pos..pos + 2).ok_or_else(err)?.try_into().unwrap();
+            Ok((u16::from_be_bytes(b) as u64, pos + 2))
+        }
+        26 => {
+            let b: [u8; 4] = bytes.get(pos..pos + 4).ok_or_else(err)?.try_into().unwrap();
+            Ok((u32::from_be_bytes(b) as u64, pos + 4))
+        }
+        27 => {
+            let b: [u8; 8] = bytes.get(pos..pos + 8).ok_or_else(err)?.try_into().unwrap();
+            Ok((u64::from_be_bytes(b), pos + 8))
+        }
+        31 => Ok((0, pos)),
+        _ => Err(SignerError::SigningFailed(format!("invalid CBOR argument encoding: {info}"))),
+    }
+}
+
+/// Walks a single CBOR data item starting at `start` purely to bound its
+/// nesting depth; it does not need to understand the item's meaning, only
+/// to refuse to recurse past `MAX_CBOR_NESTING_DEPTH`.
+fn check_item_depth(bytes: &[u8], start: usize, depth: u32) -> SignerResult<usize> {
+    if depth > MAX_CBOR_NESTING_DEPTH {
+        return Err(SignerError::SigningFailed(format!(
+            "CBOR nesting exceeds the {MAX_CBOR_NESTING_DEPTH}-level limit"
+        )));
+    }
+    let err = || SignerError::SigningFailed("truncated cbor".to_string());
+    let head = *bytes.get(start).ok_or_else(err)?;
+    let major = head >> 5;
+    let info = head & 0x1f;
+    let (arg, pos) = cbor_argument(bytes, start + 1, info)?;
+
+    match major {
+        0 | 1 | 7 => Ok(pos),
+        2 | 3 => {
+            if info == 31 {
+                check_indefinite_depth(bytes, pos, depth + 1)
+            } else {
+                let end = pos.checked_add(arg as usize).ok_or_else(err)?;
+                if end > bytes.len() {
+                    return Err(err());
+                }
+                Ok(end)
+            }
+        }
+        4 => {
+            if info == 31 {
+                check_indefinite_depth(bytes, pos, depth + 1)
+            } else {
+                let mut pos = pos;
+                for _ in 0..arg {
+                    pos = check_item_depth(bytes, pos, depth + 1)?;
+                }
+                Ok(pos)
+            }
+        }
+        5 => {
+            if info == 31 {
+                check_indefinite_depth(bytes, pos, depth + 1)
+            } else {
+                let mut pos = pos;
+                for _ in 0..arg {
+                    pos = check_item_depth(bytes, pos, depth + 1)?; // key
+                    pos = check_item_depth(bytes, pos, depth + 1)?; // value
+                }
+                Ok(pos)
+            }
+        }
+        6 => check_item_depth(bytes, pos, depth + 1),
+        _ => unreachable!("major type is at most 7"),
+    }
+}
+
+fn check_indefinite_depth(bytes: &[u8], mut pos: usize, depth: u32) -> SignerResult<usize> {
+    loop {
+        match bytes.get(pos) {
+            Some(0xFF) => return Ok(pos + 1),
+            Some(_) => pos = check_item_depth(bytes, pos, depth)?,
+            None => return Err(SignerError::SigningFailed("truncated cbor".to_string())),
+        }
+    }
+}
+
+fn decode_cbor(bytes: &[u8]) -> SignerResult<Value> {
+    check_item_depth(bytes, 0, 0)?;
+    ciborium::de::from_reader(bytes)
+        .map_err(|e| SignerError::SigningFailed(format!("malformed cbor: {e}")))
+}
+
+fn verifying_key(public_key: &[u8]) -> SignerResult<VerifyingKey> {
+    VerifyingKey::try_from(public_key)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid ed25519 public key: {e}")))
+}
+
+fn signature(bytes: &[u8]) -> SignerResult<Signature> {
+    Signature::try_from(bytes)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid ed25519 signature: {e}")))
+}
+
+/// Verifies a raw Ed25519 signature over `message_hex`.
+pub fn verify_signature(
+    public_key_hex: &str,
+    message_hex: &str,
+    signature_hex: &str,
+) -> SignerResult<bool> {
+    let public_key = hex::decode(public_key_hex)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid public key hex: {e}")))?;
+    let message = hex::decode(message_hex)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid message hex: {e}")))?;
+    let signature_bytes = hex::decode(signature_hex)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid signature hex: {e}")))?;
+
+    let verifying_key = verifying_key(&public_key)?;
+    let signature = signature(&signature_bytes)?;
+    Ok(verifying_key.verify(&message, &signature).is_ok())
+}
+
+fn cose_key_public_key(cose_key_hex: &str) -> SignerResult<Vec<u8>> {
+    let bytes = hex::decode(cose_key_hex)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid COSE_Key hex: {e}")))?;
+
+    match decode_cbor(&bytes)? {
+        Value::Map(entries) => entries
+            .into_iter()
+            .find(|(key, _)| matches!(key, Value::Integer(i) if *i == Integer::from(-2)))
+            .and_then(|(_, value)| match value {
+                Value::Bytes(public_key) => Some(public_key),
+                _ => None,
+            })
+            .ok_or_else(|| SignerError::SigningFailed("COSE_Key is missing a public key (-2)".to_string())),
+        _ => Err(SignerError::SigningFailed("expected a COSE_Key map".to_string())),
+    }
+}
+
+/// Verifies a CIP-8 `COSE_Sign1` message by reconstructing its
+/// `Sig_structure` and checking it against the embedded `COSE_Key`.
+pub fn verify_data(cose_sign1_hex: &str, cose_key_hex: &str) -> SignerResult<bool> {
+    let bytes = hex::decode(cose_sign1_hex)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid COSE_Sign1 hex: {e}")))?;
+
+    let items = match decode_cbor(&bytes)? {
+        Value::Array(items) if items.len() == 4 => items,
+        _ => {
+            return Err(SignerError::SigningFailed(
+                "expected a 4-element COSE_Sign1 array".to_string(),
+            ))
+        }
+    };
+
+    let as_bytes = |value: &Value, field: &str| match value {
+        Value::Bytes(b) => Ok(b.clone()),
+        _ => Err(SignerError::SigningFailed(format!("COSE_Sign1 {field} is not a byte string"))),
+    };
+    let protected = as_bytes(&items[0], "protected header")?;
+    let payload = as_bytes(&items[2], "payload")?;
+    let signature_bytes = as_bytes(&items[3], "signature")?;
+
+    let public_key = cose_key_public_key(cose_key_hex)?;
+    let to_verify = cose::sig_structure_bytes(&protected, &payload)?;
+
+    let verifying_key = verifying_key(&public_key)?;
+    let signature = signature(&signature_bytes)?;
+    Ok(verifying_key.verify(&to_verify, &signature).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer as _, SigningKey};
+
+    fn keypair(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    #[test]
+    fn verify_signature_accepts_a_genuine_signature() {
+        let signing_key = keypair(7);
+        let message = b"hello cardano";
+        let signature = signing_key.sign(message);
+
+        let ok = verify_signature(
+            &hex::encode(signing_key.verifying_key().to_bytes()),
+            &hex::encode(message),
+            &hex::encode(signature.to_bytes()),
+        )
+        .unwrap();
+        assert!(ok);
+    }
+
+    #[test]
+    fn verify_signature_rejects_a_tampered_message() {
+        let signing_key = keypair(7);
+        let signature = signing_key.sign(b"hello cardano");
+
+        let ok = verify_signature(
+            &hex::encode(signing_key.verifying_key().to_bytes()),
+            &hex::encode(b"goodbye cardano"),
+            &hex::encode(signature.to_bytes()),
+        )
+        .unwrap();
+        assert!(!ok);
+    }
+
+    #[test]
+    fn verify_data_round_trips_with_cose_sign_data() {
+        let signing_key = keypair(9);
+        let public_key = signing_key.verifying_key().to_bytes();
+
+        let (cose_sign1_hex, cose_key_hex) = cose::sign_data(
+            |data| signing_key.sign(data).to_bytes().to_vec(),
+            &public_key,
+            &[1u8; 29],
+            b"sign me in",
+            false,
+        )
+        .unwrap();
+
+        assert!(verify_data(&cose_sign1_hex, &cose_key_hex).unwrap());
+    }
+
+    #[test]
+    fn verify_data_rejects_cbor_nested_past_the_depth_limit() {
+        // Each `0x81` is a 1-element array header: a run of them nests one
+        // level per byte with no payload, the shape the depth cap exists to
+        // reject before it can recurse the process into a stack overflow.
+        let too_deep = hex::encode(vec![0x81u8; (MAX_CBOR_NESTING_DEPTH as usize) + 16]);
+        let err = verify_data(&too_deep, &too_deep).unwrap_err();
+        assert!(err.to_string().contains("nesting"));
+    }
+
+    #[test]
+    fn verify_data_rejects_a_mismatched_key() {
+        let signing_key = keypair(9);
+        let other_public_key = keypair(10).verifying_key().to_bytes();
+
+        let (cose_sign1_hex, _) = cose::sign_data(
+            |data| signing_key.sign(data).to_bytes().to_vec(),
+            &signing_key.verifying_key().to_bytes(),
+            &[1u8; 29],
+            b"sign me in",
+            false,
+        )
+        .unwrap();
+        let (_, wrong_cose_key_hex) =
+            cose::sign_data(|data| signing_key.sign(data).to_bytes().to_vec(), &other_public_key, &[1u8; 29], b"sign me in", false).unwrap();
+
+        assert!(!verify_data(&cose_sign1_hex, &wrong_cose_key_hex).unwrap());
+    }
+}
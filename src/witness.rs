@@ -0,0 +1,370 @@
+//! CBOR-level helpers for M-of-N native-script multisig signing: producing
+//! just one signer's vkey witness for a transaction, and merging several
+//! signers' witnesses into a single transaction's witness set.
+//!
+//! A transaction is `[body, witness_set, ...]` with the body embedded
+//! directly (not wrapped in a byte string), so unlike `cose.rs` we cannot
+//! decode it into a `ciborium::Value` and re-encode it to hash or re-emit:
+//! ciborium's re-serialization is canonical and is not guaranteed to
+//! reproduce the original bytes (indefinite-length items, non-minimal
+//! integers, etc. all decode fine but round-trip to a different encoding).
+//! Since other signers in an M-of-N setup may be built with different
+//! tooling, a byte-level divergence here would mean this crate signs (and
+//! re-emits) a transaction hash that diverges from the one everyone else,
+//! and the chain, uses. Instead we locate the original byte span of each
+//! top-level array item and only ever touch the witness-set span, splicing
+//! its replacement back into the untouched surrounding bytes.
+
+use blake2::digest::{Update, VariableOutput};
+use blake2::Blake2bVar;
+use ciborium::value::{Integer, Value};
+
+use crate::error::{SignerError, SignerResult};
+
+/// Caps how deeply nested arrays/maps/tags may be before `item_end` gives up,
+/// so a few hundred KB of nested single-byte array headers (each one byte,
+/// each one more stack frame) can't stack-overflow the process walking
+/// attacker-supplied `tx_hex`/witness CBOR. Cardano transactions never come
+/// close to this in practice.
+const MAX_CBOR_NESTING_DEPTH: u32 = 64;
+
+/// Reads a CBOR major-type argument (the length/value encoded in the
+/// initial byte's low 5 bits, spilling into following bytes for info
+/// 24..=27), returning `(value, position after the argument)`. `31`
+/// (indefinite-length marker) is returned as `0` — callers must check
+/// `info == 31` separately before using the value.
+fn read_argument(bytes: &[u8], pos: usize, info: u8) -> SignerResult<(u64, usize)> {
+    let err = || SignerError::InvalidTxHex("truncated CBOR transaction".to_string());
+    match info {
+        0..=23 => Ok((info as u64, pos)),
+        24 => Ok((*bytes.get(pos).ok_or_else(err)? as u64, pos + 1)),
+        25 => {
+            let b: [u8; 2] = bytes.get(pos..pos + 2).ok_or_else(err)?.try_into().unwrap();
+            Ok((u16::from_be_bytes(b) as u64, pos + 2))
+        }
+        26 => {
+            let b: [u8; 4] = bytes.get(pos..pos + 4).ok_or_else(err)?.try_into().unwrap();
+            Ok((u32::from_be_bytes(b) as u64, pos + 4))
+        }
+        27 => {
+            let b: [u8; 8] = bytes.get(pos..pos + 8).ok_or_else(err)?.try_into().unwrap();
+            Ok((u64::from_be_bytes(b), pos + 8))
+        }
+        31 => Ok((0, pos)),
+        _ => Err(SignerError::InvalidTxHex(format!("invalid CBOR argument encoding: {info}"))),
+    }
+}
+
+/// Returns the byte offset just past the single CBOR data item starting at
+/// `start`, without decoding it into a `Value` (so the source bytes are
+/// never touched, only walked). `depth` is the nesting level of this item
+/// (0 for a top-level array element); exceeding `MAX_CBOR_NESTING_DEPTH`
+/// fails fast instead of recursing further.
+fn item_end(bytes: &[u8], start: usize, depth: u32) -> SignerResult<usize> {
+    let err = || SignerError::InvalidTxHex("truncated CBOR transaction".to_string());
+    if depth > MAX_CBOR_NESTING_DEPTH {
+        return Err(SignerError::InvalidTxHex(format!(
+            "CBOR nesting exceeds the {MAX_CBOR_NESTING_DEPTH}-level limit"
+        )));
+    }
+    let head = *bytes.get(start).ok_or_else(err)?;
+    let major = head >> 5;
+    let info = head & 0x1f;
+    let (arg, pos) = read_argument(bytes, start + 1, info)?;
+
+    match major {
+        0 | 1 | 7 => Ok(pos),
+        2 | 3 => {
+            if info == 31 {
+                skip_indefinite_sequence(bytes, pos, depth + 1)
+            } else {
+                let end = pos.checked_add(arg as usize).ok_or_else(err)?;
+                if end > bytes.len() {
+                    return Err(err());
+                }
+                Ok(end)
+            }
+        }
+        4 => {
+            if info == 31 {
+                skip_indefinite_sequence(bytes, pos, depth + 1)
+            } else {
+                let mut pos = pos;
+                for _ in 0..arg {
+                    pos = item_end(bytes, pos, depth + 1)?;
+                }
+                Ok(pos)
+            }
+        }
+        5 => {
+            if info == 31 {
+                skip_indefinite_sequence(bytes, pos, depth + 1)
+            } else {
+                let mut pos = pos;
+                for _ in 0..arg {
+                    pos = item_end(bytes, pos, depth + 1)?; // key
+                    pos = item_end(bytes, pos, depth + 1)?; // value
+                }
+                Ok(pos)
+            }
+        }
+        6 => item_end(bytes, pos, depth + 1),
+        _ => unreachable!("major type is at most 7"),
+    }
+}
+
+/// Walks an indefinite-length array/map/string starting right after its
+/// opening byte, stopping at (and consuming) the `0xFF` break. `depth` is
+/// the nesting level of the items inside this sequence.
+fn skip_indefinite_sequence(bytes: &[u8], mut pos: usize, depth: u32) -> SignerResult<usize> {
+    loop {
+        match bytes.get(pos) {
+            Some(0xFF) => return Ok(pos + 1),
+            Some(_) => pos = item_end(bytes, pos, depth)?,
+            None => return Err(SignerError::InvalidTxHex("truncated CBOR transaction".to_string())),
+        }
+    }
+}
+
+/// Returns the `(start, end)` byte span of each top-level item in the CBOR
+/// array at the front of `bytes`, verifying the array runs exactly to the
+/// end of `bytes` with nothing trailing.
+fn array_item_spans(bytes: &[u8]) -> SignerResult<Vec<(usize, usize)>> {
+    let err = || SignerError::InvalidTxHex("truncated CBOR transaction".to_string());
+    let head = *bytes.first().ok_or_else(err)?;
+    if head >> 5 != 4 {
+        return Err(SignerError::InvalidTxHex(
+            "expected a [body, witness_set, ...] transaction array".to_string(),
+        ));
+    }
+    let info = head & 0x1f;
+    let (count, mut pos) = read_argument(bytes, 1, info)?;
+
+    let mut spans = Vec::new();
+    if info == 31 {
+        while bytes.get(pos) != Some(&0xFF) {
+            let start = pos;
+            pos = item_end(bytes, pos, 0)?;
+            spans.push((start, pos));
+        }
+        pos += 1;
+    } else {
+        for _ in 0..count {
+            let start = pos;
+            pos = item_end(bytes, pos, 0)?;
+            spans.push((start, pos));
+        }
+    }
+
+    if pos != bytes.len() {
+        return Err(SignerError::InvalidTxHex(
+            "trailing bytes after transaction array".to_string(),
+        ));
+    }
+    Ok(spans)
+}
+
+fn decode_tx_bytes(tx_hex: &str) -> SignerResult<Vec<u8>> {
+    hex::decode(tx_hex).map_err(|e| SignerError::InvalidTxHex(format!("invalid transaction hex: {e}")))
+}
+
+fn encode(value: &Value) -> SignerResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes)
+        .map_err(|e| SignerError::SigningFailed(format!("failed to CBOR-encode: {e}")))?;
+    Ok(bytes)
+}
+
+fn is_key(key: &Value, n: i32) -> bool {
+    matches!(key, Value::Integer(i) if *i == Integer::from(n))
+}
+
+fn blake2b_256(data: &[u8]) -> Vec<u8> {
+    let mut hasher = Blake2bVar::new(32).expect("32 is a valid blake2b output size");
+    hasher.update(data);
+    let mut out = vec![0u8; 32];
+    hasher.finalize_variable(&mut out).expect("fixed-size output buffer");
+    out
+}
+
+/// Hashes `tx_hex`'s body (the literal original bytes of the transaction
+/// array's first element, never decoded and re-encoded) so the caller can
+/// sign it and build a vkey witness with `encode_vkey_witness`.
+pub fn hash_for_signing(tx_hex: &str) -> SignerResult<Vec<u8>> {
+    let tx_bytes = decode_tx_bytes(tx_hex)?;
+    let spans = array_item_spans(&tx_bytes)?;
+    let (start, end) = *spans
+        .first()
+        .ok_or_else(|| SignerError::InvalidTxHex("transaction is missing a body".to_string()))?;
+    Ok(blake2b_256(&tx_bytes[start..end]))
+}
+
+/// CBOR-encodes a `[vkey, signature]` vkey witness.
+pub fn encode_vkey_witness(public_key: &[u8], signature: &[u8]) -> SignerResult<String> {
+    let witness = Value::Array(vec![Value::Bytes(public_key.to_vec()), Value::Bytes(signature.to_vec())]);
+    Ok(hex::encode(encode(&witness)?))
+}
+
+fn decode_vkey_witness(witness_hex: &str) -> SignerResult<(Vec<u8>, Value)> {
+    let bytes = hex::decode(witness_hex)
+        .map_err(|e| SignerError::SigningFailed(format!("invalid vkey witness hex: {e}")))?;
+    let value: Value = ciborium::de::from_reader(bytes.as_slice())
+        .map_err(|e| SignerError::SigningFailed(format!("malformed vkey witness cbor: {e}")))?;
+    match &value {
+        Value::Array(pair) if pair.len() == 2 => match &pair[0] {
+            Value::Bytes(public_key) => Ok((public_key.clone(), value)),
+            _ => Err(SignerError::SigningFailed(
+                "vkey witness public key is not a byte string".to_string(),
+            )),
+        },
+        _ => Err(SignerError::SigningFailed(
+            "expected a [vkey, signature] vkey witness".to_string(),
+        )),
+    }
+}
+
+/// Merges `witnesses` into `tx_hex`'s witness set (CBOR map key `0`),
+/// de-duplicating by public key so the same signer's witness is never
+/// attached twice.
+///
+/// Only the witness-set span of `tx_hex` is decoded and re-encoded; the
+/// transaction body and everything after the witness set (validity
+/// interval, auxiliary data, ...) are spliced back in verbatim from the
+/// original bytes so the body hash every party signed over is preserved.
+pub fn combine_witnesses(tx_hex: &str, witnesses: &[String]) -> SignerResult<String> {
+    let tx_bytes = decode_tx_bytes(tx_hex)?;
+    let spans = array_item_spans(&tx_bytes)?;
+    let (wit_start, wit_end) = *spans.get(1).ok_or_else(|| {
+        SignerError::InvalidTxHex("transaction is missing a witness set".to_string())
+    })?;
+
+    let witness_set: Value = ciborium::de::from_reader(&tx_bytes[wit_start..wit_end])
+        .map_err(|e| SignerError::InvalidTxHex(format!("malformed witness set cbor: {e}")))?;
+    let mut witness_set = match witness_set {
+        Value::Map(entries) => entries,
+        _ => {
+            return Err(SignerError::InvalidTxHex(
+                "expected a transaction witness set map".to_string(),
+            ))
+        }
+    };
+
+    let mut vkey_witnesses: Vec<(Vec<u8>, Value)> = witness_set
+        .iter()
+        .find(|(key, _)| is_key(key, 0))
+        .map(|(_, value)| match value {
+            Value::Array(existing) => existing
+                .iter()
+                .filter_map(|w| match w {
+                    Value::Array(pair) if pair.len() == 2 => match &pair[0] {
+                        Value::Bytes(public_key) => Some((public_key.clone(), w.clone())),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .unwrap_or_default();
+
+    for witness_hex in witnesses {
+        let (public_key, value) = decode_vkey_witness(witness_hex)?;
+        if !vkey_witnesses.iter().any(|(existing, _)| existing == &public_key) {
+            vkey_witnesses.push((public_key, value));
+        }
+    }
+
+    let merged = Value::Array(vkey_witnesses.into_iter().map(|(_, value)| value).collect());
+    if let Some(entry) = witness_set.iter_mut().find(|(key, _)| is_key(key, 0)) {
+        entry.1 = merged;
+    } else {
+        witness_set.push((Value::Integer(0.into()), merged));
+    }
+    let new_witness_set_bytes = encode(&Value::Map(witness_set))?;
+
+    let mut output = Vec::with_capacity(tx_bytes.len() + new_witness_set_bytes.len());
+    output.extend_from_slice(&tx_bytes[..wit_start]);
+    output.extend_from_slice(&new_witness_set_bytes);
+    output.extend_from_slice(&tx_bytes[wit_end..]);
+    Ok(hex::encode(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cbor(value: &Value) -> Vec<u8> {
+        encode(value).unwrap()
+    }
+
+    fn pair(pubkey: u8, sig: u8) -> Value {
+        Value::Array(vec![Value::Bytes(vec![pubkey; 32]), Value::Bytes(vec![sig; 64])])
+    }
+
+    fn tx(body: Value, witness_set: Value) -> Vec<u8> {
+        cbor(&Value::Array(vec![body, witness_set, Value::Bool(true), Value::Null]))
+    }
+
+    #[test]
+    fn hash_for_signing_hashes_only_the_body_span() {
+        let body = Value::Map(vec![(Value::Integer(0.into()), Value::Text("inputs".to_string()))]);
+        let witness_set = Value::Map(vec![]);
+        let body_bytes = cbor(&body);
+        let tx_bytes = tx(body, witness_set);
+
+        let hash = hash_for_signing(&hex::encode(&tx_bytes)).unwrap();
+        assert_eq!(hash, blake2b_256(&body_bytes));
+    }
+
+    #[test]
+    fn combine_witnesses_preserves_body_bytes_verbatim() {
+        let body = Value::Map(vec![(Value::Integer(0.into()), Value::Text("inputs".to_string()))]);
+        let witness_set = Value::Map(vec![(Value::Integer(0.into()), Value::Array(vec![pair(1, 1)]))]);
+        let body_bytes = cbor(&body);
+        let tx_bytes = tx(body, witness_set);
+
+        let combined_hex = combine_witnesses(&hex::encode(&tx_bytes), &[hex::encode(cbor(&pair(2, 2)))]).unwrap();
+        let combined_bytes = hex::decode(combined_hex).unwrap();
+        let spans = array_item_spans(&combined_bytes).unwrap();
+        let (start, end) = spans[0];
+        assert_eq!(&combined_bytes[start..end], body_bytes.as_slice());
+    }
+
+    #[test]
+    fn hash_for_signing_rejects_cbor_nested_past_the_depth_limit() {
+        // Each `0x81` is a 1-element array header, so this nests one level
+        // per byte with no payload at all -- exactly the "few hundred KB of
+        // nested array headers" shape the depth cap exists to reject before
+        // it can recurse the process into a stack overflow.
+        let too_deep = vec![0x81u8; (MAX_CBOR_NESTING_DEPTH as usize) + 16];
+        let err = hash_for_signing(&hex::encode(&too_deep)).unwrap_err();
+        assert!(err.to_string().contains("nesting"));
+    }
+
+    #[test]
+    fn combine_witnesses_dedupes_by_public_key() {
+        let body = Value::Map(vec![]);
+        let witness_set = Value::Map(vec![(Value::Integer(0.into()), Value::Array(vec![pair(1, 1)]))]);
+        let tx_bytes = tx(body, witness_set);
+
+        let combined_hex = combine_witnesses(
+            &hex::encode(&tx_bytes),
+            &[hex::encode(cbor(&pair(1, 9))), hex::encode(cbor(&pair(2, 2)))],
+        )
+        .unwrap();
+        let combined_bytes = hex::decode(combined_hex).unwrap();
+        let value: Value = ciborium::de::from_reader(combined_bytes.as_slice()).unwrap();
+        let items = match value {
+            Value::Array(items) => items,
+            _ => panic!("expected array"),
+        };
+        let witnesses = match &items[1] {
+            Value::Map(entries) => entries.iter().find(|(k, _)| is_key(k, 0)).unwrap().1.clone(),
+            _ => panic!("expected map"),
+        };
+        match witnesses {
+            Value::Array(witnesses) => assert_eq!(witnesses.len(), 2),
+            _ => panic!("expected array"),
+        }
+    }
+}